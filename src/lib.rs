@@ -1,10 +1,13 @@
 //! A rolling file appender with customizable rolling conditions.
 //! Includes built-in support for rolling conditions on date/time
-//! (daily, hourly, every minute) and/or size.
+//! (every minute, hourly, daily, weekly, monthly) and/or size.
 //!
 //! Follows a Debian-style naming convention for logfiles,
 //! using basename, basename.1, ..., basename.N where N is
-//! the maximum number of allowed historical logfiles.
+//! the maximum number of allowed historical logfiles. A
+//! timestamp-keyed naming scheme is also available via
+//! [`FileNaming`]/[`TimestampNaming`] for archives named after
+//! their rollover time instead.
 //!
 //! This is useful to combine with the tracing crate and
 //! tracing_appender::non_blocking::NonBlocking -- use it
@@ -25,6 +28,7 @@
 #![deny(warnings)]
 
 use chrono::prelude::*;
+use chrono::Duration;
 use std::{
     convert::TryFrom,
     fs::{self, File, OpenOptions},
@@ -32,18 +36,102 @@ use std::{
     path::Path,
 };
 
+/// Supplies the "current" time used when deciding whether to roll over.
+/// Defaults to the real system clock, but can be swapped out (e.g. for a
+/// [`ManualClock`]) so that rollover boundaries are testable. Every clock
+/// yields a `DateTime<Local>`, and period boundaries are computed in the
+/// local timezone; a UTC or other fixed-offset timezone cannot be selected.
+#[derive(Debug, Default)]
+pub enum Clock {
+    /// Uses `Local::now()`.
+    #[default]
+    System,
+    /// Uses a fixed time that must be advanced manually via
+    /// [`ManualClock::set_now`].
+    Manual(ManualClock),
+}
+
+impl Clock {
+    /// Returns the current time according to this clock.
+    fn now(&self) -> DateTime<Local> {
+        match self {
+            Clock::System => Local::now(),
+            Clock::Manual(c) => c.now(),
+        }
+    }
+}
+
+/// A clock whose time is set explicitly by the caller. Useful in tests that
+/// need deterministic control over rollover boundaries (including
+/// midnight/hour/minute edge cases) through the real `io::Write` entrypoint,
+/// or in production for callers who want to drive rollovers off a time
+/// source other than the local system clock.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now: std::sync::Mutex<Option<DateTime<Local>>>,
+}
+
+impl ManualClock {
+    /// Constructs a new manual clock with no time set yet.
+    pub fn new() -> ManualClock {
+        ManualClock::default()
+    }
+
+    /// Sets the time that will be returned by subsequent calls to `now()`.
+    pub fn set_now(&self, now: DateTime<Local>) {
+        *self.now.lock().unwrap() = Some(now);
+    }
+
+    fn now(&self) -> DateTime<Local> {
+        self.now.lock().unwrap().expect("ManualClock::set_now must be called before use")
+    }
+}
+
 /// Determines when a file should be "rolled over".
 pub trait RollingCondition {
     /// Determine and return whether or not the file should be rolled over.
     fn should_rollover(&mut self, now: &DateTime<Local>, current_filesize: u64) -> bool;
+
+    /// The time-based frequency this condition rolls over on, if any.
+    /// Consulted by timestamp-keyed `FileNaming` schemes to pick a format.
+    /// Defaults to `None`.
+    fn frequency(&self) -> Option<RollingFrequency> {
+        None
+    }
+}
+
+/// Determines whether and how rotated-out files are compressed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum Compression {
+    /// Rotated files are kept as plain, uncompressed files.
+    #[default]
+    None,
+    /// Rotated files are compressed with gzip and given a `.gz` extension.
+    Gzip,
+    /// Rotated files are compressed with zstd and given a `.zst` extension.
+    Zstd,
+}
+
+impl Compression {
+    /// The filename extension used for files compressed with this mode,
+    /// or `None` if files are not compressed.
+    fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
 }
 
 /// Determines how often a file should be rolled over
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum RollingFrequency {
-    EveryDay,
-    EveryHour,
     EveryMinute,
+    EveryHour,
+    EveryDay,
+    EveryWeek,
+    EveryMonth,
 }
 
 impl RollingFrequency {
@@ -51,40 +139,180 @@ impl RollingFrequency {
     /// different files.
     pub fn equivalent_datetime(&self, dt: &DateTime<Local>) -> DateTime<Local> {
         match self {
-            RollingFrequency::EveryDay => Local.ymd(dt.year(), dt.month(), dt.day()).and_hms(0, 0, 0),
-            RollingFrequency::EveryHour => Local.ymd(dt.year(), dt.month(), dt.day()).and_hms(dt.hour(), 0, 0),
             RollingFrequency::EveryMinute => {
                 Local
                     .ymd(dt.year(), dt.month(), dt.day())
                     .and_hms(dt.hour(), dt.minute(), 0)
             },
+            RollingFrequency::EveryHour => Local.ymd(dt.year(), dt.month(), dt.day()).and_hms(dt.hour(), 0, 0),
+            RollingFrequency::EveryDay => Local.ymd(dt.year(), dt.month(), dt.day()).and_hms(0, 0, 0),
+            RollingFrequency::EveryWeek => {
+                let start_of_day = Local.ymd(dt.year(), dt.month(), dt.day()).and_hms(0, 0, 0);
+                start_of_day - Duration::days(dt.weekday().num_days_from_monday() as i64)
+            },
+            RollingFrequency::EveryMonth => Local.ymd(dt.year(), dt.month(), 1).and_hms(0, 0, 0),
+        }
+    }
+
+    /// Calculates the instant at which the period containing `dt` ends and
+    /// the next one begins, i.e. the first instant for which
+    /// `equivalent_datetime` would return something different than it does
+    /// for `dt`.
+    pub fn next_boundary(&self, dt: &DateTime<Local>) -> DateTime<Local> {
+        let current = self.equivalent_datetime(dt);
+        match self {
+            RollingFrequency::EveryMinute => current + Duration::minutes(1),
+            RollingFrequency::EveryHour => current + Duration::hours(1),
+            RollingFrequency::EveryDay => current + Duration::days(1),
+            RollingFrequency::EveryWeek => current + Duration::weeks(1),
+            RollingFrequency::EveryMonth => {
+                let (year, month) = if dt.month() == 12 { (dt.year() + 1, 1) } else { (dt.year(), dt.month() + 1) };
+                Local.ymd(year, month, 1).and_hms(0, 0, 0)
+            },
+        }
+    }
+}
+
+/// Determines how an archived (rotated-out) file is named.
+pub trait FileNaming: std::fmt::Debug {
+    /// Returns the path an archived file should have.
+    ///
+    /// For index-based schemes, `n` (1-based) is the slot the file is
+    /// rotating into, and `rollover_at`/`frequency` are ignored. For
+    /// timestamp-keyed schemes, `rollover_at` and `frequency` determine the
+    /// name, and `n` instead disambiguates multiple rollovers landing on
+    /// the same formatted timestamp: the first occurrence (`n == 1`) gets
+    /// no suffix, later ones append one so they don't collide.
+    fn archived_path(
+        &self,
+        base_filename: &str,
+        n: usize,
+        rollover_at: &DateTime<Local>,
+        frequency: Option<RollingFrequency>,
+        compression: Compression,
+    ) -> String;
+
+    /// Whether archived files following this scheme are numbered in a
+    /// fixed sequence (`true`, Debian-style) or must instead be discovered
+    /// by scanning the parent directory for matching names (`false`,
+    /// timestamp-keyed).
+    fn is_indexed(&self) -> bool;
+}
+
+/// Debian-style naming: `basename`, `basename.1`, ..., `basename.N`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub struct IndexedNaming;
+
+impl FileNaming for IndexedNaming {
+    fn archived_path(
+        &self,
+        base_filename: &str,
+        n: usize,
+        _rollover_at: &DateTime<Local>,
+        _frequency: Option<RollingFrequency>,
+        compression: Compression,
+    ) -> String {
+        match compression.extension() {
+            Some(ext) => format!("{}.{}.{}", base_filename, n, ext),
+            None => format!("{}.{}", base_filename, n),
+        }
+    }
+
+    fn is_indexed(&self) -> bool {
+        true
+    }
+}
+
+/// Names archived files after the instant they were rolled over at, e.g.
+/// `myprogram.2021-03-30` (daily) or `myprogram.2021-03-30-14` (hourly).
+/// This is the naming convention used by `tracing-appender`. Because the
+/// embedded timestamp already sorts lexicographically in calendar order,
+/// archives are never renamed once written; only several rollovers within
+/// one period get a numeric suffix (see `archived_path`).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub struct TimestampNaming;
+
+impl TimestampNaming {
+    fn format_str(frequency: Option<RollingFrequency>) -> &'static str {
+        match frequency {
+            Some(RollingFrequency::EveryMinute) => "%Y-%m-%d-%H-%M",
+            Some(RollingFrequency::EveryHour) => "%Y-%m-%d-%H",
+            Some(RollingFrequency::EveryDay) | None => "%Y-%m-%d",
+            Some(RollingFrequency::EveryWeek) => "%G-W%V",
+            Some(RollingFrequency::EveryMonth) => "%Y-%m",
         }
     }
 }
 
+impl FileNaming for TimestampNaming {
+    fn archived_path(
+        &self,
+        base_filename: &str,
+        n: usize,
+        rollover_at: &DateTime<Local>,
+        frequency: Option<RollingFrequency>,
+        compression: Compression,
+    ) -> String {
+        let mut stamped = format!("{}.{}", base_filename, rollover_at.format(Self::format_str(frequency)));
+        // n == 1 is the common case and keeps the plain timestamped name;
+        // later occurrences within the same period append `.1`, `.2`, ...
+        // so a second same-period rollover can't silently overwrite the
+        // first (see `RollingFileAppender::next_archive_index`).
+        if n > 1 {
+            stamped = format!("{}.{}", stamped, n - 1);
+        }
+        match compression.extension() {
+            Some(ext) => format!("{}.{}", stamped, ext),
+            None => stamped,
+        }
+    }
+
+    fn is_indexed(&self) -> bool {
+        false
+    }
+}
+
 /// Writes data to a file, and "rolls over" to preserve older data in
-/// a separate set of files. Old files have a Debian-style naming scheme
-/// where we have base_filename, base_filename.1, ..., base_filename.N
-/// where N is the maximum number of rollover files to keep.
+/// a separate set of files. By default, old files have a Debian-style
+/// naming scheme where we have base_filename, base_filename.1, ...,
+/// base_filename.N where N is the maximum number of rollover files to
+/// keep; see `FileNaming` for alternatives.
 #[derive(Debug)]
-pub struct RollingFileAppender<RC>
+pub struct RollingFileAppender<RC, FN = IndexedNaming>
 where
     RC: RollingCondition,
+    FN: FileNaming,
 {
     condition: RC,
     filename: String,
     max_filecount: usize,
     current_filesize: u64,
     writer_opt: Option<BufWriter<File>>,
+    compression: Compression,
+    clock: Clock,
+    naming: FN,
+    /// The instant the current file started being written to, used by
+    /// timestamp-keyed naming schemes to name it once it's archived.
+    period_start: Option<DateTime<Local>>,
+    /// When set, enforces a hard cap on output file size; see
+    /// `hard_size_cap`.
+    hard_size_cap: Option<u64>,
+    /// Whether `hard_size_cap` is allowed to split a write in the middle
+    /// of a line; see `avoid_splitting_lines`.
+    avoid_splitting_lines: bool,
 }
 
-impl<RC> RollingFileAppender<RC>
+impl<RC> RollingFileAppender<RC, IndexedNaming>
 where
     RC: RollingCondition,
 {
     /// Creates a new rolling file appender with the given condition.
     /// The filename parent path must already exist.
-    pub fn new(filename: impl AsRef<Path>, condition: RC, max_filecount: usize) -> io::Result<RollingFileAppender<RC>> {
+    pub fn new(
+        filename: impl AsRef<Path>,
+        condition: RC,
+        max_filecount: usize,
+    ) -> io::Result<RollingFileAppender<RC, IndexedNaming>> {
         let filename = filename.as_ref().to_str().unwrap().to_string();
         let mut appender = RollingFileAppender {
             condition,
@@ -92,29 +320,130 @@ where
             max_filecount,
             current_filesize: 0,
             writer_opt: None,
+            compression: Compression::None,
+            clock: Clock::default(),
+            naming: IndexedNaming,
+            period_start: None,
+            hard_size_cap: None,
+            avoid_splitting_lines: false,
         };
         // Fail if we can't open the file initially...
         appender.open_writer_if_needed()?;
         Ok(appender)
     }
+}
+
+impl<RC, FN> RollingFileAppender<RC, FN>
+where
+    RC: RollingCondition,
+    FN: FileNaming,
+{
+    /// Switches to a different naming scheme for archived files.
+    pub fn with_naming<FN2: FileNaming>(self, naming: FN2) -> RollingFileAppender<RC, FN2> {
+        RollingFileAppender {
+            condition: self.condition,
+            filename: self.filename,
+            max_filecount: self.max_filecount,
+            current_filesize: self.current_filesize,
+            writer_opt: self.writer_opt,
+            compression: self.compression,
+            clock: self.clock,
+            naming,
+            period_start: self.period_start,
+            hard_size_cap: self.hard_size_cap,
+            avoid_splitting_lines: self.avoid_splitting_lines,
+        }
+    }
+
+    /// Enables compression of rotated-out files using the given scheme.
+    /// The current, active file (n==0) is never compressed; it is only
+    /// compressed once it is rotated out of slot 0.
+    pub fn compress(mut self, compression: Compression) -> RollingFileAppender<RC, FN> {
+        self.compression = compression;
+        self
+    }
+
+    /// Overrides the clock used to determine the "current" time in the
+    /// `io::Write` entrypoint. Useful (e.g. in tests) for driving rollover
+    /// boundaries deterministically. Boundaries are still computed in the
+    /// local timezone whichever clock is set.
+    pub fn with_clock(mut self, clock: Clock) -> RollingFileAppender<RC, FN> {
+        self.clock = clock;
+        self
+    }
+
+    /// Enables a hard cap on output file size, opt-in on top of whatever
+    /// `RollingCondition` is configured. Normally a file's size is only
+    /// checked *before* a write using the pre-write `current_filesize`, so
+    /// a single large write can push a file well past `max_size`, and the
+    /// first write after a rollover is never checked at all. With a hard
+    /// cap set, `write_with_datetime` instead rolls over as soon as
+    /// `current_filesize + buf.len()` would exceed `max_size`, splitting
+    /// an oversized buffer across as many rollovers as needed so that no
+    /// output file exceeds the cap. By default a split may land in the
+    /// middle of a line; pair with
+    /// [`RollingFileAppender::avoid_splitting_lines`] to prefer the last
+    /// newline that still fits instead.
+    pub fn hard_size_cap(mut self, max_size: u64) -> RollingFileAppender<RC, FN> {
+        self.hard_size_cap = Some(max_size);
+        self
+    }
+
+    /// When used together with [`RollingFileAppender::hard_size_cap`],
+    /// avoids splitting a single write in the middle of a line: a chunk
+    /// that would otherwise end mid-line is shortened to end at the last
+    /// `\n` it contains, pushing the remainder into the next file. Has no
+    /// effect without a hard size cap, and a chunk containing no `\n`
+    /// within budget is still written whole (and may exceed the cap)
+    /// rather than being split blindly or dropped.
+    pub fn avoid_splitting_lines(mut self) -> RollingFileAppender<RC, FN> {
+        self.avoid_splitting_lines = true;
+        self
+    }
 
-    /// Determines the final filename, where n==0 indicates the current file
+    /// Determines the final filename, where n==0 indicates the current file.
+    /// For n>0, delegates to the configured `FileNaming` scheme.
     fn filename_for(&self, n: usize) -> String {
-        let f = self.filename.clone();
         if n > 0 {
-            format!("{}.{}", f, n)
+            // Indexed naming ignores the timestamp entirely, so don't
+            // touch the clock to produce one -- `Clock::Manual` may not
+            // have a time set yet, and callers that only ever drive
+            // rotation through `write_with_datetime` never otherwise
+            // touch it.
+            let rollover_at =
+                if self.naming.is_indexed() { Local.timestamp(0, 0) } else { self.rollover_reference_time() };
+            self.naming.archived_path(&self.filename, n, &rollover_at, self.condition.frequency(), self.compression)
         } else {
-            f
+            self.filename.clone()
         }
     }
 
-    /// Rotates old files to make room for a new one.
-    /// This may result in the deletion of the oldest file
-    fn rotate_files(&mut self) -> io::Result<()> {
+    /// The reference time used when a naming scheme needs one but the
+    /// caller isn't rotating right now (e.g. ad-hoc `filename_for` lookups).
+    fn rollover_reference_time(&self) -> DateTime<Local> {
+        self.clock.now()
+    }
+
+    /// Rotates old files to make room for a new one, enforcing
+    /// `max_filecount`. The just-closed current file is archived (see
+    /// `archive_current_file`), compressing it first if compression is
+    /// enabled.
+    fn rotate_files(&mut self, rollover_at: &DateTime<Local>) -> io::Result<()> {
+        if self.naming.is_indexed() {
+            self.rotate_indexed_files(rollover_at)
+        } else {
+            self.archive_current_file(rollover_at)?;
+            self.prune_archived_files()
+        }
+    }
+
+    /// Debian-style rotation: shifts `.1 .. .N` up by one slot, dropping
+    /// the oldest, then archives the current file into slot 1.
+    fn rotate_indexed_files(&mut self, rollover_at: &DateTime<Local>) -> io::Result<()> {
         // ignore any failure removing the oldest file (may not exist)
         let _ = fs::remove_file(self.filename_for(self.max_filecount.max(1)));
         let mut r = Ok(());
-        for i in (0..self.max_filecount.max(1)).rev() {
+        for i in (1..self.max_filecount.max(1)).rev() {
             let rotate_from = self.filename_for(i);
             let rotate_to = self.filename_for(i + 1);
             if let Err(e) = fs::rename(&rotate_from, &rotate_to).or_else(|e| match e.kind() {
@@ -126,17 +455,162 @@ where
                 r = Err(e);
             }
         }
+        if let Err(e) = self.archive_current_file(rollover_at) {
+            r = Err(e);
+        }
         r
     }
 
+    /// Archives the just-closed current file under the name the naming
+    /// scheme assigns to the instant the file was opened (`period_start`,
+    /// or `rollover_at` if that is unset), compressing it along the way if
+    /// compression is enabled. If compression fails for any reason, falls
+    /// back to keeping the plain archived file so that no data is lost.
+    ///
+    /// For indexed naming the target is always slot 1, since
+    /// `rotate_indexed_files` already shifted any existing slot 1 out of
+    /// the way. For timestamp-keyed naming, multiple rollovers can land on
+    /// the same formatted timestamp (e.g. several `max_size`/
+    /// `hard_size_cap` rollovers within one day), so the target is instead
+    /// the first occurrence of that timestamp not already present on disk
+    /// -- otherwise the later rollover would silently overwrite the
+    /// earlier one via `rename`.
+    fn archive_current_file(&mut self, rollover_at: &DateTime<Local>) -> io::Result<()> {
+        // Timestamp-keyed schemes name the archive after the period the
+        // file actually covers, which started when it was opened -- not
+        // the instant we're rolling over at now.
+        let named_at = self.period_start.unwrap_or(*rollover_at);
+        let current = self.filename_for(0);
+        let n = if self.naming.is_indexed() { 1 } else { self.next_archive_index(&named_at) };
+        let target = self
+            .naming
+            .archived_path(&self.filename, n, &named_at, self.condition.frequency(), self.compression);
+        if self.compression == Compression::None || !Path::new(&current).exists() {
+            return fs::rename(&current, target).or_else(|e| match e.kind() {
+                io::ErrorKind::NotFound => Ok(()),
+                _ => Err(e),
+            });
+        }
+        match self.compress_file(&current, &target) {
+            Ok(()) => {
+                let _ = fs::remove_file(&current);
+                Ok(())
+            },
+            Err(e) => {
+                eprintln!(
+                    "WARNING: Failed to compress rotated logfile {}: {}; keeping it uncompressed",
+                    current, e
+                );
+                let fallback =
+                    self.naming
+                        .archived_path(&self.filename, n, &named_at, self.condition.frequency(), Compression::None);
+                fs::rename(&current, fallback)
+            },
+        }
+    }
+
+    /// Finds the smallest `n >= 1` whose timestamp-keyed archived path
+    /// (honoring the current compression mode) doesn't already exist,
+    /// so a second rollover landing on the same formatted timestamp gets
+    /// its own file instead of overwriting the first one. Only meaningful
+    /// for non-indexed naming; indexed naming always archives into the
+    /// fixed slot 1 that `rotate_indexed_files` just vacated.
+    fn next_archive_index(&self, named_at: &DateTime<Local>) -> usize {
+        let mut n = 1;
+        while Path::new(&self.naming.archived_path(&self.filename, n, named_at, self.condition.frequency(), self.compression))
+            .exists()
+        {
+            n += 1;
+        }
+        n
+    }
+
+    /// Enforces `max_filecount` for non-indexed (timestamp-keyed) naming
+    /// schemes by scanning the parent directory for files sharing our
+    /// basename and deleting the oldest ones beyond the limit. Relies on
+    /// the naming scheme producing names that sort lexicographically in
+    /// chronological order.
+    fn prune_archived_files(&self) -> io::Result<()> {
+        let base_path = Path::new(&self.filename);
+        let parent = base_path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+        let base_name = match base_path.file_name().and_then(|f| f.to_str()) {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let prefix = format!("{}.", base_name);
+        let mut archived: Vec<_> = fs::read_dir(parent)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.file_name().and_then(|f| f.to_str()).is_some_and(|name| name.starts_with(&prefix)))
+            .collect();
+        archived.sort();
+        while archived.len() > self.max_filecount.max(1) {
+            let oldest = archived.remove(0);
+            let _ = fs::remove_file(oldest);
+        }
+        Ok(())
+    }
+
+    /// Streams `from` through the configured compressor into `to`. Opens
+    /// the files lazily inside each feature-gated arm so that building
+    /// with neither `gzip` nor `zstd` enabled doesn't leave `from`/`to`
+    /// unused.
+    fn compress_file(&self, from: &str, to: &str) -> io::Result<()> {
+        match self.compression {
+            Compression::None => unreachable!("compress_file is only called when compression is enabled"),
+            Compression::Gzip => {
+                #[cfg(feature = "gzip")]
+                {
+                    let mut input = File::open(from)?;
+                    let output = File::create(to)?;
+                    let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+                    io::copy(&mut input, &mut encoder)?;
+                    encoder.finish()?;
+                    Ok(())
+                }
+                #[cfg(not(feature = "gzip"))]
+                {
+                    let _ = (from, to);
+                    Err(io::Error::new(io::ErrorKind::Unsupported, "gzip compression requires the \"gzip\" feature"))
+                }
+            },
+            Compression::Zstd => {
+                #[cfg(feature = "zstd")]
+                {
+                    let mut input = File::open(from)?;
+                    let output = File::create(to)?;
+                    let mut encoder = zstd::Encoder::new(output, 0)?;
+                    io::copy(&mut input, &mut encoder)?;
+                    encoder.finish()?;
+                    Ok(())
+                }
+                #[cfg(not(feature = "zstd"))]
+                {
+                    let _ = (from, to);
+                    Err(io::Error::new(io::ErrorKind::Unsupported, "zstd compression requires the \"zstd\" feature"))
+                }
+            },
+        }
+    }
+
     /// Forces a rollover to happen immediately.
     pub fn rollover(&mut self) -> io::Result<()> {
+        let now = self.clock.now();
+        self.rollover_at(&now)
+    }
+
+    /// Forces a rollover to happen immediately, archiving the current file
+    /// under the given instant.
+    fn rollover_at(&mut self, rollover_at: &DateTime<Local>) -> io::Result<()> {
         // Before closing, make sure all data is flushed successfully.
         self.flush()?;
         // We must close the current file before rotating files
         self.writer_opt.take();
         self.current_filesize = 0;
-        self.rotate_files()?;
+        self.rotate_files(rollover_at)?;
+        // The file we just rotated away carried `period_start`; the next
+        // write into the fresh file starts a new period.
+        self.period_start = None;
         self.open_writer_if_needed()
     }
 
@@ -166,7 +640,7 @@ where
     /// Writes data using the given datetime to calculate the rolling condition
     pub fn write_with_datetime(&mut self, buf: &[u8], now: &DateTime<Local>) -> io::Result<usize> {
         if self.condition.should_rollover(now, self.current_filesize) {
-            if let Err(e) = self.rollover() {
+            if let Err(e) = self.rollover_at(now) {
                 // If we can't rollover, just try to continue writing anyway
                 // (better than missing data).
                 // This will likely used to implement logging, so
@@ -174,7 +648,45 @@ where
                 eprintln!("WARNING: Failed to rotate logfile {}: {}", self.filename, e);
             }
         }
+        if self.period_start.is_none() {
+            self.period_start = Some(*now);
+        }
         self.open_writer_if_needed()?;
+        let Some(max_size) = self.hard_size_cap else {
+            return self.write_chunk(buf);
+        };
+        let mut remaining = buf;
+        let mut total_written = 0;
+        loop {
+            let budget = usize::try_from(max_size.saturating_sub(self.current_filesize)).unwrap_or(usize::MAX);
+            let mut split_at = self.hard_cap_split_at(remaining, budget);
+            // Guarantee forward progress even when a single line can't be
+            // made to fit under the cap at all, e.g. a cap smaller than
+            // one line, or too small to hold even one byte.
+            if split_at == 0 && self.current_filesize == 0 {
+                split_at = remaining.len();
+            }
+            let (chunk, rest) = remaining.split_at(split_at);
+            total_written += self.write_chunk(chunk)?;
+            remaining = rest;
+            if remaining.is_empty() {
+                break;
+            }
+            if let Err(e) = self.rollover_at(now) {
+                eprintln!("WARNING: Failed to rotate logfile {}: {}", self.filename, e);
+                total_written += self.write_chunk(remaining)?;
+                break;
+            }
+            if self.period_start.is_none() {
+                self.period_start = Some(*now);
+            }
+        }
+        Ok(total_written)
+    }
+
+    /// Writes `buf` to the current file in one shot and updates
+    /// `current_filesize`, without any rollover/size-cap bookkeeping.
+    fn write_chunk(&mut self, buf: &[u8]) -> io::Result<usize> {
         if let Some(writer) = self.writer_opt.as_mut() {
             let buf_len = buf.len();
             writer.write_all(buf).map(|_| {
@@ -188,14 +700,32 @@ where
             ))
         }
     }
+
+    /// Determines how many leading bytes of `remaining` to write in the
+    /// current pass so the file doesn't grow past `budget` more bytes,
+    /// honoring `avoid_splitting_lines` by preferring the last `\n` within
+    /// budget over a mid-line split. Splits at `budget` when the option is
+    /// off. When it's on but no newline is found within budget, returns 0
+    /// so the line starts the next file; the caller then writes it whole
+    /// into the empty file rather than looping forever or losing data.
+    fn hard_cap_split_at(&self, remaining: &[u8], budget: usize) -> usize {
+        if remaining.len() <= budget {
+            return remaining.len();
+        }
+        if self.avoid_splitting_lines {
+            return remaining[..budget].iter().rposition(|&b| b == b'\n').map_or(0, |pos| pos + 1);
+        }
+        budget
+    }
 }
 
-impl<RC> io::Write for RollingFileAppender<RC>
+impl<RC, FN> io::Write for RollingFileAppender<RC, FN>
 where
     RC: RollingCondition,
+    FN: FileNaming,
 {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let now = Local::now();
+        let now = self.clock.now();
         self.write_with_datetime(buf, &now)
     }
 
@@ -209,3 +739,8 @@ where
 
 pub mod base;
 pub use base::*;
+
+#[cfg(feature = "tracing-subscriber")]
+pub mod writer;
+#[cfg(feature = "tracing-subscriber")]
+pub use writer::*;
@@ -0,0 +1,79 @@
+//! Lets a [`RollingFileAppender`] be plugged directly into
+//! `tracing_subscriber::fmt().with_writer(...)`, without wiring up
+//! `tracing_appender::non_blocking::NonBlocking` separately.
+
+use crate::{FileNaming, IndexedNaming, RollingCondition, RollingFileAppender};
+use std::io;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// A synchronous, self-contained rolling sink suitable for
+/// `tracing_subscriber::fmt().with_writer(...)`. Holds the appender
+/// behind a shared mutex so that `make_writer` calls from concurrent
+/// threads stay consistent with the appender's rollover/size bookkeeping.
+#[derive(Clone, Debug)]
+pub struct RollingWriter<RC, FN = IndexedNaming>
+where
+    RC: RollingCondition,
+    FN: FileNaming,
+{
+    appender: Arc<Mutex<RollingFileAppender<RC, FN>>>,
+}
+
+impl<RC, FN> RollingWriter<RC, FN>
+where
+    RC: RollingCondition,
+    FN: FileNaming,
+{
+    /// Wraps an existing appender for use as a `MakeWriter`.
+    pub fn new(appender: RollingFileAppender<RC, FN>) -> RollingWriter<RC, FN> {
+        RollingWriter {
+            appender: Arc::new(Mutex::new(appender)),
+        }
+    }
+}
+
+impl<'a, RC, FN> MakeWriter<'a> for RollingWriter<RC, FN>
+where
+    RC: RollingCondition + Send + 'static,
+    FN: FileNaming + Send + 'static,
+{
+    type Writer = RollingWriterGuard<RC, FN>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RollingWriterGuard {
+            appender: Arc::clone(&self.appender),
+        }
+    }
+}
+
+/// The `io::Write` handle returned by [`RollingWriter::make_writer`].
+/// Forwards every write to the shared appender under its mutex.
+#[derive(Debug)]
+pub struct RollingWriterGuard<RC, FN>
+where
+    RC: RollingCondition,
+    FN: FileNaming,
+{
+    appender: Arc<Mutex<RollingFileAppender<RC, FN>>>,
+}
+
+impl<RC, FN> io::Write for RollingWriterGuard<RC, FN>
+where
+    RC: RollingCondition,
+    FN: FileNaming,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.appender
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.appender
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .flush()
+    }
+}